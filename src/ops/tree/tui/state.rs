@@ -1,12 +1,48 @@
+use std::collections::{HashSet, VecDeque};
 use std::path::PathBuf;
 
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use regex::Regex;
 
-use crate::core::DependencyTree;
+use crate::core::{DependencyTree, NodeId};
 
 use super::widget::TreeWidgetState;
 
+/// Cap on how many committed queries `search_history` keeps.
+const MAX_SEARCH_HISTORY: usize = 50;
+
+/// A single search match: which node matched, how well it matched, and which
+/// characters of its name were responsible, so the widget can render them in
+/// a distinct style.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub node_id: NodeId,
+    pub score: i32,
+    pub matched_indices: Vec<usize>,
+}
+
+/// Which algorithm `perform_search` uses to match `search_query` against the
+/// tree.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Fuzzy subsequence matching, scored and ranked.
+    #[default]
+    Fuzzy,
+    /// The query is compiled as a regular expression and matched against
+    /// crate names.
+    Regex,
+}
+
+/// A single attribute of a node that a search query can be restricted to via
+/// a `field:term` prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchField {
+    Name,
+    Version,
+    Source,
+}
+
 #[derive(Debug)]
 pub struct TuiState {
     pub running: bool,
@@ -15,8 +51,28 @@ pub struct TuiState {
     pub show_help: bool,
     pub search_active: bool,
     pub search_query: String,
-    pub search_results: Vec<crate::core::NodeId>,
+    pub search_results: Vec<SearchMatch>,
     pub search_result_index: Option<usize>,
+    /// Which matching algorithm `search_query` is interpreted with.
+    pub search_mode: SearchMode,
+    /// Set when `search_mode` is `Regex` and `search_query` fails to
+    /// compile; `search_results` is left untouched while this is set.
+    pub search_error: Option<String>,
+    /// When `true`, the tree is pruned down to matches and the ancestors
+    /// needed to reach them, instead of just highlighting matches in place.
+    pub filter_active: bool,
+    /// Nodes left visible while `filter_active` is set: every match plus all
+    /// of its ancestors. `None` when no filter is applied, in which case
+    /// every node is visible.
+    pub visible_nodes: Option<HashSet<NodeId>>,
+    /// Expansion state saved from before the filter was turned on, restored
+    /// once the filter is cleared.
+    filter_saved_expanded: Option<HashSet<NodeId>>,
+    /// Queries committed with Enter, most recent first, deduplicated.
+    search_history: VecDeque<String>,
+    /// Position within `search_history` while scrolling it into
+    /// `search_query`; `None` means the user isn't currently browsing it.
+    search_history_index: Option<usize>,
 }
 
 impl TuiState {
@@ -33,6 +89,13 @@ impl TuiState {
             search_query: String::new(),
             search_results: Vec::new(),
             search_result_index: None,
+            search_mode: SearchMode::default(),
+            search_error: None,
+            filter_active: false,
+            visible_nodes: None,
+            filter_saved_expanded: None,
+            search_history: Self::load_search_history(),
+            search_history_index: None,
         })
     }
 
@@ -46,7 +109,8 @@ impl TuiState {
         if self.search_active {
             match (key_event.code, key_event.modifiers) {
                 (KeyCode::Enter, _) => {
-                    // Exit search mode but keep highlights
+                    // Commit the query to history, then exit search mode but keep highlights
+                    self.commit_search_history();
                     self.search_active = false;
                 }
                 (KeyCode::Esc, _) => {
@@ -56,11 +120,29 @@ impl TuiState {
                 (KeyCode::Backspace, _) => {
                     // Remove last character from search query
                     self.search_query.pop();
+                    self.search_history_index = None;
                     self.perform_search();
                 }
+                (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                    // Toggle between fuzzy and regex search modes
+                    self.search_mode = match self.search_mode {
+                        SearchMode::Fuzzy => SearchMode::Regex,
+                        SearchMode::Regex => SearchMode::Fuzzy,
+                    };
+                    self.perform_search();
+                }
+                (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                    // Recall the previous (older) history entry into the query
+                    self.next_search_history();
+                }
+                (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                    // Recall the next (more recent) history entry into the query
+                    self.prev_search_history();
+                }
                 (KeyCode::Char(c), _) if c != '/' => {
                     // Add character to search query
                     self.search_query.push(c);
+                    self.search_history_index = None;
                     self.perform_search();
                 }
                 (KeyCode::Down, _) => {
@@ -80,6 +162,7 @@ impl TuiState {
                     self.search_active = true;
                     self.search_query.clear();
                     self.search_result_index = None;
+                    self.search_history_index = None;
                 }
                 (KeyCode::Char('n'), _) => {
                     // Go to next search result
@@ -93,6 +176,10 @@ impl TuiState {
                     // Clear search highlights
                     self.clear_search();
                 }
+                (KeyCode::Char('f'), _) => {
+                    // Toggle filter mode: prune the tree to matches and their ancestors
+                    self.toggle_filter();
+                }
                 (KeyCode::Char('q'), _) => {
                     self.running = false;
                 }
@@ -100,21 +187,23 @@ impl TuiState {
                     self.show_help = !self.show_help;
                 }
                 (KeyCode::Char('p'), _) => {
-                    self.tree_widget_state.select_parent(&self.dependency_tree);
+                    self.tree_widget_state
+                        .select_parent(&self.dependency_tree, self.visible_nodes.as_ref());
                 }
                 (KeyCode::Char(']'), _) => {
                     self.tree_widget_state
-                        .select_next_sibling(&self.dependency_tree);
+                        .select_next_sibling(&self.dependency_tree, self.visible_nodes.as_ref());
                 }
                 (KeyCode::Char('['), _) => {
                     self.tree_widget_state
-                        .select_previous_sibling(&self.dependency_tree);
+                        .select_previous_sibling(&self.dependency_tree, self.visible_nodes.as_ref());
                 }
                 (KeyCode::Down, _) => {
                     if self.search_active {
                         self.next_search_result();
                     } else {
-                        self.tree_widget_state.select_next(&self.dependency_tree);
+                        self.tree_widget_state
+                            .select_next(&self.dependency_tree, self.visible_nodes.as_ref());
                     }
                 }
                 (KeyCode::Up, _) => {
@@ -122,14 +211,16 @@ impl TuiState {
                         self.prev_search_result();
                     } else {
                         self.tree_widget_state
-                            .select_previous(&self.dependency_tree);
+                            .select_previous(&self.dependency_tree, self.visible_nodes.as_ref());
                     }
                 }
                 (KeyCode::PageDown, _) => {
-                    self.tree_widget_state.page_down(&self.dependency_tree);
+                    self.tree_widget_state
+                        .page_down(&self.dependency_tree, self.visible_nodes.as_ref());
                 }
                 (KeyCode::PageUp, _) => {
-                    self.tree_widget_state.page_up(&self.dependency_tree);
+                    self.tree_widget_state
+                        .page_up(&self.dependency_tree, self.visible_nodes.as_ref());
                 }
                 (KeyCode::Right, _) => {
                     self.tree_widget_state.expand(&self.dependency_tree);
@@ -144,59 +235,259 @@ impl TuiState {
 }
 
 impl TuiState {
-    /// Checks if needle is a subsequence of haystack (character order matching).
-    fn is_subsequence_match(needle: &str, haystack: &str) -> bool {
+    /// Scores `needle` as a fuzzy subsequence match against `haystack`, or
+    /// returns `None` if it isn't a subsequence at all.
+    fn fuzzy_match(needle: &str, haystack: &str) -> Option<(i32, Vec<usize>)> {
+        const START_OF_WORD_BONUS: i32 = 10;
+        const CONSECUTIVE_BONUS: i32 = 5;
+        const GAP_PENALTY: i32 = 1;
+
         let needle_lower = needle.to_lowercase();
         let haystack_lower = haystack.to_lowercase();
-        
-        let mut needle_chars = needle_lower.chars();
-        let mut current_needle_char = needle_chars.next();
-        
-        for haystack_char in haystack_lower.chars() {
-            if let Some(n) = current_needle_char {
-                if n == haystack_char {
-                    current_needle_char = needle_chars.next();
+        let haystack_chars: Vec<char> = haystack_lower.chars().collect();
+
+        let mut needle_chars = needle_lower.chars().peekable();
+        let mut matched_indices = Vec::new();
+        let mut score = 0;
+        let mut prev_matched_index: Option<usize> = None;
+
+        for (index, &haystack_char) in haystack_chars.iter().enumerate() {
+            match needle_chars.peek() {
+                Some(&needle_char) if needle_char == haystack_char => {
+                    needle_chars.next();
+
+                    let starts_word =
+                        index == 0 || matches!(haystack_chars[index - 1], '-' | '_');
+                    let is_consecutive =
+                        prev_matched_index.is_some() && prev_matched_index == index.checked_sub(1);
+
+                    score += 1;
+                    if starts_word {
+                        score += START_OF_WORD_BONUS;
+                    }
+                    if is_consecutive {
+                        score += CONSECUTIVE_BONUS;
+                    }
+
+                    matched_indices.push(index);
+                    prev_matched_index = Some(index);
+                }
+                _ => {
+                    if prev_matched_index.is_some() {
+                        score -= GAP_PENALTY;
+                    }
                 }
             }
         }
-        
-        current_needle_char.is_none()
+
+        if needle_chars.peek().is_none() {
+            Some((score, matched_indices))
+        } else {
+            None
+        }
     }
 
-    /// Performs a search across all nodes in the dependency tree.
+    /// Performs a search across all nodes in the dependency tree, using
+    /// whichever algorithm `search_mode` selects.
     fn perform_search(&mut self) {
         if self.search_query.is_empty() {
             self.search_results.clear();
             self.search_result_index = None;
+            self.search_error = None;
+            // No query to filter down to: show the full tree again.
+            self.deactivate_filter();
             return;
         }
 
-        let query = &self.search_query;
-        let mut results = Vec::new();
-
-        // Search through all nodes in the tree
-        for (index, node) in self.dependency_tree.nodes.iter().enumerate() {
-            let node_id = crate::core::NodeId(index);
-            
-            // Check if the name matches the query (using both prefix and subsequence matching)
-            if Self::is_subsequence_match(query, &node.name) {
-                results.push(node_id);
+        let results = match self.search_mode {
+            SearchMode::Fuzzy => {
+                self.search_error = None;
+                self.fuzzy_search_results()
             }
-        }
+            SearchMode::Regex => match self.regex_search_results() {
+                Ok(results) => {
+                    self.search_error = None;
+                    results
+                }
+                Err(err) => {
+                    // Keep the previous results and let the prompt show the error.
+                    self.search_error = Some(err.to_string());
+                    return;
+                }
+            },
+        };
 
         self.search_results = results;
-        self.search_result_index = if self.search_results.is_empty() { 
-            None 
-        } else { 
-            Some(0) 
+        self.search_result_index = if self.search_results.is_empty() {
+            None
+        } else {
+            Some(0)
         };
 
         // Select the first match if available
-        if let Some(&first_match) = self.search_results.first() {
-            self.tree_widget_state.selected = Some(first_match);
+        if let Some(first_match) = self.search_results.first() {
+            self.tree_widget_state.selected = Some(first_match.node_id);
+        }
+
+        if self.filter_active {
+            self.apply_filter();
+        }
+    }
+
+    /// Scores every node against `search_query` with the fuzzy matcher.
+    ///
+    /// The query may be a bare term, in which case it's tested against the
+    /// node's name, version, and source, with a hit in an earlier field
+    /// outranking a hit in a later one; or it may carry a `field:term`
+    /// prefix (`name:`, `version:`, `source:`) to restrict matching to a
+    /// single attribute. Results come back best match first.
+    fn fuzzy_search_results(&self) -> Vec<SearchMatch> {
+        // Each weight exceeds the sum of the weights below it by far more than
+        // any raw fuzzy score a name, version, or source string reaches, so an
+        // earlier field's hit ranks above hits confined to later fields.
+        const NAME_WEIGHT: i32 = 4_000_000;
+        const VERSION_WEIGHT: i32 = 2_000_000;
+        const SOURCE_WEIGHT: i32 = 1_000_000;
+
+        let (field, term) = Self::parse_search_query(&self.search_query);
+
+        let mut results: Vec<SearchMatch> = self
+            .dependency_tree
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| {
+                let source = node.source.as_deref().unwrap_or_default();
+
+                let (score, matched_indices) = match field {
+                    Some(SearchField::Name) => {
+                        let (score, indices) = Self::fuzzy_match(term, &node.name)?;
+                        (score + NAME_WEIGHT, indices)
+                    }
+                    Some(SearchField::Version) => {
+                        let (score, _) = Self::fuzzy_match(term, &node.version)?;
+                        (score + VERSION_WEIGHT, Vec::new())
+                    }
+                    Some(SearchField::Source) => {
+                        let (score, _) = Self::fuzzy_match(term, source)?;
+                        (score + SOURCE_WEIGHT, Vec::new())
+                    }
+                    None => {
+                        let name_match = Self::fuzzy_match(term, &node.name);
+                        let version_match = Self::fuzzy_match(term, &node.version);
+                        let source_match = Self::fuzzy_match(term, source);
+
+                        if name_match.is_none() && version_match.is_none() && source_match.is_none()
+                        {
+                            return None;
+                        }
+
+                        let mut score = 0;
+                        let mut matched_indices = Vec::new();
+                        if let Some((s, indices)) = name_match {
+                            score += s + NAME_WEIGHT;
+                            matched_indices = indices;
+                        }
+                        if let Some((s, _)) = version_match {
+                            score += s + VERSION_WEIGHT;
+                        }
+                        if let Some((s, _)) = source_match {
+                            score += s + SOURCE_WEIGHT;
+                        }
+                        (score, matched_indices)
+                    }
+                };
+
+                Some(SearchMatch {
+                    node_id: NodeId(index),
+                    score,
+                    matched_indices,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.cmp(&a.score));
+        results
+    }
+
+    /// Splits a search query into an optional `field:` prefix and the
+    /// remaining term. With an absent or unrecognized prefix there is no
+    /// field and the whole query is the term.
+    fn parse_search_query(query: &str) -> (Option<SearchField>, &str) {
+        for (prefix, field) in [
+            ("name:", SearchField::Name),
+            ("version:", SearchField::Version),
+            ("source:", SearchField::Source),
+        ] {
+            if let Some(term) = query.strip_prefix(prefix) {
+                return (Some(field), term);
+            }
+        }
+        (None, query)
+    }
+
+    /// Matches every node's name against `search_query` compiled as a
+    /// regular expression. Matches carry no score or highlighted indices.
+    fn regex_search_results(&self) -> Result<Vec<SearchMatch>, regex::Error> {
+        let regex = Regex::new(&self.search_query)?;
+        Ok(self
+            .dependency_tree
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| regex.is_match(&node.name))
+            .map(|(index, _)| SearchMatch {
+                node_id: NodeId(index),
+                score: 0,
+                matched_indices: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// Toggles filter mode. Ignored if there's no query to prune down to.
+    fn toggle_filter(&mut self) {
+        if self.filter_active {
+            self.deactivate_filter();
+        } else if !self.search_query.is_empty() {
+            self.filter_saved_expanded = Some(self.tree_widget_state.expanded.clone());
+            self.filter_active = true;
+            self.apply_filter();
         }
     }
 
+    /// Turns the filter off and restores the pre-filter expansion state.
+    fn deactivate_filter(&mut self) {
+        if !self.filter_active {
+            return;
+        }
+        self.filter_active = false;
+        self.visible_nodes = None;
+        if let Some(expanded) = self.filter_saved_expanded.take() {
+            self.tree_widget_state.expanded = expanded;
+        }
+    }
+
+    /// Recomputes `visible_nodes` from `search_results` and force-expands
+    /// every ancestor on the path to each match.
+    fn apply_filter(&mut self) {
+        let mut visible = HashSet::new();
+        for m in &self.search_results {
+            let mut current = Some(m.node_id);
+            while let Some(id) = current {
+                if !visible.insert(id) {
+                    break;
+                }
+                self.tree_widget_state.expanded.insert(id);
+                current = self.parent_of(id);
+            }
+        }
+        self.visible_nodes = Some(visible);
+    }
+
+    /// Returns the parent of `node_id`, if any.
+    fn parent_of(&self, node_id: NodeId) -> Option<NodeId> {
+        self.dependency_tree.nodes.get(node_id.0)?.parent
+    }
+
     /// Moves to the next search result.
     fn next_search_result(&mut self) {
         if self.search_results.is_empty() {
@@ -206,9 +497,9 @@ impl TuiState {
         if let Some(current_index) = self.search_result_index {
             let next_index = (current_index + 1) % self.search_results.len();
             self.search_result_index = Some(next_index);
-            
-            if let Some(&node_id) = self.search_results.get(next_index) {
-                self.tree_widget_state.selected = Some(node_id);
+
+            if let Some(m) = self.search_results.get(next_index) {
+                self.tree_widget_state.selected = Some(m.node_id);
             }
         }
     }
@@ -226,9 +517,9 @@ impl TuiState {
                 current_index - 1
             };
             self.search_result_index = Some(prev_index);
-            
-            if let Some(&node_id) = self.search_results.get(prev_index) {
-                self.tree_widget_state.selected = Some(node_id);
+
+            if let Some(m) = self.search_results.get(prev_index) {
+                self.tree_widget_state.selected = Some(m.node_id);
             }
         }
     }
@@ -239,5 +530,149 @@ impl TuiState {
         self.search_query.clear();
         self.search_results.clear();
         self.search_result_index = None;
+        self.search_history_index = None;
+        self.search_error = None;
+        self.deactivate_filter();
+    }
+
+    /// Records `search_query` in `search_history` and persists it to disk.
+    fn commit_search_history(&mut self) {
+        let query = self.search_query.trim();
+        if query.is_empty() {
+            return;
+        }
+
+        self.search_history.retain(|entry| entry != query);
+        self.search_history.push_front(query.to_string());
+        self.search_history.truncate(MAX_SEARCH_HISTORY);
+        self.search_history_index = None;
+        self.save_search_history();
+    }
+
+    /// Scrolls `search_history` towards older entries.
+    fn next_search_history(&mut self) {
+        if self.search_history.is_empty() {
+            return;
+        }
+
+        let next_index = match self.search_history_index {
+            None => 0,
+            Some(index) if index + 1 < self.search_history.len() => index + 1,
+            Some(index) => index,
+        };
+        self.search_history_index = Some(next_index);
+        self.recall_search_history(next_index);
+    }
+
+    /// Scrolls `search_history` towards more recent entries.
+    fn prev_search_history(&mut self) {
+        match self.search_history_index {
+            None => {}
+            Some(0) => {
+                self.search_history_index = None;
+                self.search_query.clear();
+                self.perform_search();
+            }
+            Some(index) => {
+                self.search_history_index = Some(index - 1);
+                self.recall_search_history(index - 1);
+            }
+        }
+    }
+
+    /// Loads `search_history[index]` into `search_query` and re-searches.
+    fn recall_search_history(&mut self, index: usize) {
+        if let Some(entry) = self.search_history.get(index) {
+            self.search_query = entry.clone();
+            self.perform_search();
+        }
+    }
+
+    /// Path to the persisted `search_history` file under the cache dir.
+    fn search_history_path() -> Option<PathBuf> {
+        let mut path = dirs::cache_dir()?;
+        path.push("cargo-tree-tui");
+        path.push("search_history");
+        Some(path)
+    }
+
+    /// Loads persisted search history, treating missing/unreadable as empty.
+    fn load_search_history() -> VecDeque<String> {
+        let Some(path) = Self::search_history_path() else {
+            return VecDeque::new();
+        };
+        std::fs::read_to_string(path)
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Best-effort persistence of `search_history`; failures are ignored.
+    fn save_search_history(&self) {
+        let Some(path) = Self::search_history_path() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            if std::fs::create_dir_all(parent).is_err() {
+                return;
+            }
+        }
+        let contents = self
+            .search_history
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        let _ = std::fs::write(path, contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_requires_full_subsequence() {
+        assert!(TuiState::fuzzy_match("xyz", "serde").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_rewards_start_of_word() {
+        let (prefix_score, _) = TuiState::fuzzy_match("se", "serde").unwrap();
+        let (mid_score, _) = TuiState::fuzzy_match("rd", "serde").unwrap();
+        assert!(prefix_score > mid_score);
+    }
+
+    #[test]
+    fn fuzzy_match_rewards_consecutive_runs_over_scattered_hits() {
+        let (consecutive_score, _) = TuiState::fuzzy_match("ser", "serde").unwrap();
+        let (scattered_score, _) = TuiState::fuzzy_match("sre", "serde").unwrap();
+        assert!(consecutive_score > scattered_score);
+    }
+
+    #[test]
+    fn fuzzy_match_returns_matched_indices() {
+        let (_, indices) = TuiState::fuzzy_match("sd", "serde").unwrap();
+        assert_eq!(indices, vec![0, 3]);
+    }
+
+    #[test]
+    fn parse_search_query_recognizes_field_prefixes() {
+        assert_eq!(
+            TuiState::parse_search_query("name:serde"),
+            (Some(SearchField::Name), "serde")
+        );
+        assert_eq!(
+            TuiState::parse_search_query("version:1.0"),
+            (Some(SearchField::Version), "1.0")
+        );
+        assert_eq!(
+            TuiState::parse_search_query("source:crates.io"),
+            (Some(SearchField::Source), "crates.io")
+        );
+    }
+
+    #[test]
+    fn parse_search_query_bare_term_has_no_field() {
+        assert_eq!(TuiState::parse_search_query("serde"), (None, "serde"));
     }
 }
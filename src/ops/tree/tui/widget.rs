@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::{List, ListItem, ListState, StatefulWidget};
+
+use crate::core::{DependencyTree, NodeId};
+
+const PAGE_SIZE: usize = 10;
+
+/// Selection and expansion state for the dependency tree view.
+#[derive(Debug, Default)]
+pub struct TreeWidgetState {
+    pub selected: Option<NodeId>,
+    pub expanded: HashSet<NodeId>,
+}
+
+impl TreeWidgetState {
+    pub fn expand_all(&mut self, tree: &DependencyTree) {
+        self.expanded = (0..tree.nodes.len()).map(NodeId).collect();
+    }
+
+    pub fn expand(&mut self, _tree: &DependencyTree) {
+        if let Some(id) = self.selected {
+            self.expanded.insert(id);
+        }
+    }
+
+    pub fn collapse(&mut self, _tree: &DependencyTree) {
+        if let Some(id) = self.selected {
+            self.expanded.remove(&id);
+        }
+    }
+
+    pub fn select_next(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        self.move_by(tree, visible, 1);
+    }
+
+    pub fn select_previous(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        self.move_by(tree, visible, -1);
+    }
+
+    pub fn page_down(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        self.move_by(tree, visible, PAGE_SIZE as isize);
+    }
+
+    pub fn page_up(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        self.move_by(tree, visible, -(PAGE_SIZE as isize));
+    }
+
+    pub fn select_parent(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        let Some(id) = self.selected else { return };
+        let Some(parent) = tree.nodes.get(id.0).and_then(|n| n.parent) else {
+            return;
+        };
+        if visible.map_or(true, |v| v.contains(&parent)) {
+            self.selected = Some(parent);
+        }
+    }
+
+    pub fn select_next_sibling(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) {
+        self.select_sibling(tree, visible, 1);
+    }
+
+    pub fn select_previous_sibling(
+        &mut self,
+        tree: &DependencyTree,
+        visible: Option<&HashSet<NodeId>>,
+    ) {
+        self.select_sibling(tree, visible, -1);
+    }
+
+    fn select_sibling(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>, step: isize) {
+        let Some(id) = self.selected else { return };
+        let parent = tree.nodes.get(id.0).and_then(|n| n.parent);
+        let siblings: Vec<NodeId> = tree
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.parent == parent)
+            .map(|(i, _)| NodeId(i))
+            .filter(|sid| visible.map_or(true, |v| v.contains(sid)))
+            .collect();
+        let Some(pos) = siblings.iter().position(|&s| s == id) else {
+            return;
+        };
+        let next = (pos as isize + step).clamp(0, siblings.len() as isize - 1) as usize;
+        self.selected = Some(siblings[next]);
+    }
+
+    /// Renders the tree, skipping any node not present in `visible` (when set)
+    /// and selecting/scrolling to match `self.selected`.
+    pub fn render(
+        &mut self,
+        tree: &DependencyTree,
+        visible: Option<&HashSet<NodeId>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let rows = self.rows(tree, visible);
+        let items: Vec<ListItem> = rows
+            .iter()
+            .map(|&id| {
+                let node = &tree.nodes[id.0];
+                let indent = "  ".repeat(self.depth(tree, id));
+                ListItem::new(format!("{indent}{} v{}", node.name, node.version))
+            })
+            .collect();
+
+        let mut list_state = ListState::default();
+        if let Some(selected) = self.selected {
+            list_state.select(rows.iter().position(|&id| id == selected));
+        }
+
+        StatefulWidget::render(List::new(items), area, buf, &mut list_state);
+    }
+
+    /// Rows in display order: ancestors must be expanded, and the node itself
+    /// must be in `visible` when a filter is active.
+    fn rows(&self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>) -> Vec<NodeId> {
+        tree.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, _)| NodeId(i))
+            .filter(|&id| visible.map_or(true, |v| v.contains(&id)))
+            .filter(|&id| self.ancestors_expanded(tree, id))
+            .collect()
+    }
+
+    fn ancestors_expanded(&self, tree: &DependencyTree, id: NodeId) -> bool {
+        let mut current = tree.nodes.get(id.0).and_then(|n| n.parent);
+        while let Some(parent) = current {
+            if !self.expanded.contains(&parent) {
+                return false;
+            }
+            current = tree.nodes.get(parent.0).and_then(|n| n.parent);
+        }
+        true
+    }
+
+    fn depth(&self, tree: &DependencyTree, id: NodeId) -> usize {
+        let mut depth = 0;
+        let mut current = tree.nodes.get(id.0).and_then(|n| n.parent);
+        while let Some(parent) = current {
+            depth += 1;
+            current = tree.nodes.get(parent.0).and_then(|n| n.parent);
+        }
+        depth
+    }
+
+    fn move_by(&mut self, tree: &DependencyTree, visible: Option<&HashSet<NodeId>>, delta: isize) {
+        let rows = self.rows(tree, visible);
+        if rows.is_empty() {
+            return;
+        }
+        let current_pos = self
+            .selected
+            .and_then(|id| rows.iter().position(|&row| row == id));
+        let next_pos = match current_pos {
+            Some(pos) => (pos as isize + delta).clamp(0, rows.len() as isize - 1) as usize,
+            None => 0,
+        };
+        self.selected = Some(rows[next_pos]);
+    }
+}